@@ -1,5 +1,56 @@
-use std::cmp::min;
-use std::convert::From;
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+#![cfg_attr(feature = "simd-nightly", feature(portable_simd))]
+
+extern crate alloc;
+#[cfg(any(feature = "std", test))]
+extern crate std;
+
+use alloc::vec::Vec;
+use core::cmp::max;
+use core::cmp::min;
+
+// Two opt-in levels of row-construction fast path, both purely a
+// construction-time optimization (same public API, same results either
+// way):
+//
+// - `simd`: stable-toolchain-compatible. Unrolls the fold so LLVM can
+//   auto-vectorize it.
+// - `simd-nightly`: requires a nightly toolchain. Uses `core::simd`
+//   (`#![feature(portable_simd)]`) for an explicit SIMD fold over
+//   primitive integer widths, which is faster than auto-vectorization but
+//   won't build on stable.
+#[cfg(any(feature = "simd", feature = "simd-nightly"))]
+mod simd;
+
+/// A jagged 2D table -- rows of varying length, appended once in
+/// construction order -- stored as one contiguous allocation plus per-row
+/// offsets, instead of a `Vec<Vec<T>>`. This avoids one allocation (and
+/// pointer chase) per row, which matters both for query-time cache
+/// locality and for keeping the table usable in `alloc`-only (`no_std`)
+/// contexts.
+struct FlatRows<T> {
+    data: Vec<T>,
+    offsets: Vec<usize>,
+}
+
+impl<T> FlatRows<T> {
+    fn new() -> Self {
+        FlatRows { data: Vec::new(), offsets: alloc::vec![0] }
+    }
+
+    fn push_row(&mut self, row: Vec<T>) {
+        self.data.extend(row);
+        self.offsets.push(self.data.len());
+    }
+
+    fn row(&self, level: usize) -> &[T] {
+        &self.data[self.offsets[level]..self.offsets[level + 1]]
+    }
+
+    fn len(&self) -> usize {
+        self.offsets.len() - 1
+    }
+}
 
 fn highest_bit(mut x: u64) -> usize {
     if x == 0 {
@@ -18,69 +69,614 @@ fn highest_bit(mut x: u64) -> usize {
     ans
 }
 
-pub struct SparseTable<T> {
-    table: Vec<Vec<T>>,
-    row: Vec<usize>
+/// An associative binary operation over `T`:
+/// `combine(&a, &combine(&b, &c)) == combine(&combine(&a, &b), &c)`.
+pub trait AssociativeOp<T> {
+    fn combine(&self, a: &T, b: &T) -> T;
+
+    /// Combines a whole row at once: `out[i] = combine(&prev[i], &prev[i +
+    /// span])` for every `i` in `0..prev.len() - span`.
+    ///
+    /// The default just maps `combine` pairwise; implementations may
+    /// override it to process several elements at a time (see the `simd`
+    /// and `simd-nightly` fast paths for `Min`/`Max`).
+    fn combine_row(&self, prev: &[T], span: usize) -> Vec<T>
+    where
+        T: Clone,
+    {
+        prev.iter().zip(prev.iter().skip(span)).map(|(l, r)| self.combine(l, r)).collect()
+    }
+
+    /// Builds the companion "which index won" row for [`combine_row`],
+    /// given the indices that produced `prev`. Returns `None` for
+    /// operations where `combine` doesn't just return one of its two
+    /// inputs verbatim (e.g. `gcd`), so "the index of the result" isn't
+    /// meaningful.
+    ///
+    /// [`combine_row`]: AssociativeOp::combine_row
+    fn combine_row_argmin(&self, _prev: &[T], _prev_idx: &[usize], _span: usize) -> Option<Vec<usize>> {
+        None
+    }
+}
+
+/// An [`AssociativeOp`] that is additionally idempotent:
+/// `combine(&x, &x) == x` for every `x`. Idempotence is what lets
+/// `SparseTable` answer a query by combining two overlapping (rather than
+/// disjoint) ranges in O(1); operations that are only associative (sums,
+/// products, ...) need `DisjointSparseTable` instead.
+pub trait IdempotentOp<T>: AssociativeOp<T> {}
+
+/// The `min` operation, usable as the `Op` parameter of `SparseTable`.
+pub struct Min;
+
+/// The `max` operation, usable as the `Op` parameter of `SparseTable`.
+pub struct Max;
+
+#[cfg(not(any(feature = "simd", feature = "simd-nightly")))]
+impl<T> AssociativeOp<T> for Min
+where
+    T: Ord + Clone,
+{
+    fn combine(&self, a: &T, b: &T) -> T {
+        min(a, b).clone()
+    }
+
+    fn combine_row_argmin(&self, prev: &[T], prev_idx: &[usize], span: usize) -> Option<Vec<usize>> {
+        Some(
+            prev.iter()
+                .zip(prev_idx.iter())
+                .zip(prev.iter().skip(span).zip(prev_idx.iter().skip(span)))
+                .map(|((l, &li), (r, &ri))| if l <= r { li } else { ri })
+                .collect(),
+        )
+    }
+}
+
+// The stable-compatible fast path: no `TypeId` dispatch, so it needs no
+// `'static` bound beyond what `Min` already requires.
+#[cfg(all(feature = "simd", not(feature = "simd-nightly")))]
+impl<T> AssociativeOp<T> for Min
+where
+    T: Ord + Clone,
+{
+    fn combine(&self, a: &T, b: &T) -> T {
+        min(a, b).clone()
+    }
+
+    fn combine_row(&self, prev: &[T], span: usize) -> Vec<T> {
+        simd::min_row_unrolled(prev, span)
+    }
+
+    fn combine_row_argmin(&self, prev: &[T], prev_idx: &[usize], span: usize) -> Option<Vec<usize>> {
+        Some(
+            prev.iter()
+                .zip(prev_idx.iter())
+                .zip(prev.iter().skip(span).zip(prev_idx.iter().skip(span)))
+                .map(|((l, &li), (r, &ri))| if l <= r { li } else { ri })
+                .collect(),
+        )
+    }
+}
+
+// `'static` lets the `simd-nightly` fast path identify primitive `T` via
+// `TypeId`; it's only required here, rather than on the impls above, so
+// that plain and `simd` builds keep working for borrowed element types
+// such as `SparseTable<&'a i32>`.
+#[cfg(feature = "simd-nightly")]
+impl<T> AssociativeOp<T> for Min
+where
+    T: Ord + Clone + 'static,
+{
+    fn combine(&self, a: &T, b: &T) -> T {
+        min(a, b).clone()
+    }
+
+    fn combine_row(&self, prev: &[T], span: usize) -> Vec<T> {
+        simd::min_row(prev, span)
+            .unwrap_or_else(|| prev.iter().zip(prev.iter().skip(span)).map(|(l, r)| self.combine(l, r)).collect())
+    }
+
+    fn combine_row_argmin(&self, prev: &[T], prev_idx: &[usize], span: usize) -> Option<Vec<usize>> {
+        Some(
+            prev.iter()
+                .zip(prev_idx.iter())
+                .zip(prev.iter().skip(span).zip(prev_idx.iter().skip(span)))
+                .map(|((l, &li), (r, &ri))| if l <= r { li } else { ri })
+                .collect(),
+        )
+    }
+}
+
+#[cfg(not(feature = "simd-nightly"))]
+impl<T> IdempotentOp<T> for Min where T: Ord + Clone {}
+
+#[cfg(feature = "simd-nightly")]
+impl<T> IdempotentOp<T> for Min where T: Ord + Clone + 'static {}
+
+#[cfg(not(any(feature = "simd", feature = "simd-nightly")))]
+impl<T> AssociativeOp<T> for Max
+where
+    T: Ord + Clone,
+{
+    fn combine(&self, a: &T, b: &T) -> T {
+        max(a, b).clone()
+    }
 }
 
-impl<T> SparseTable<T> where T: Ord + Clone {
-    fn from_vec(seq: Vec<T>) -> Self {
+#[cfg(all(feature = "simd", not(feature = "simd-nightly")))]
+impl<T> AssociativeOp<T> for Max
+where
+    T: Ord + Clone,
+{
+    fn combine(&self, a: &T, b: &T) -> T {
+        max(a, b).clone()
+    }
+
+    fn combine_row(&self, prev: &[T], span: usize) -> Vec<T> {
+        simd::max_row_unrolled(prev, span)
+    }
+}
+
+#[cfg(feature = "simd-nightly")]
+impl<T> AssociativeOp<T> for Max
+where
+    T: Ord + Clone + 'static,
+{
+    fn combine(&self, a: &T, b: &T) -> T {
+        max(a, b).clone()
+    }
+
+    fn combine_row(&self, prev: &[T], span: usize) -> Vec<T> {
+        simd::max_row(prev, span)
+            .unwrap_or_else(|| prev.iter().zip(prev.iter().skip(span)).map(|(l, r)| self.combine(l, r)).collect())
+    }
+}
+
+#[cfg(not(feature = "simd-nightly"))]
+impl<T> IdempotentOp<T> for Max where T: Ord + Clone {}
+
+#[cfg(feature = "simd-nightly")]
+impl<T> IdempotentOp<T> for Max where T: Ord + Clone + 'static {}
+
+pub struct SparseTable<T, Op = Min> {
+    table: FlatRows<T>,
+    // `argmin.row(level)[i]`, when present, is the original index of the
+    // element `table.row(level)[i]` was folded from -- the basis for
+    // `smallest_index`. Only operations that select one of their two
+    // inputs verbatim (see `combine_row_argmin`) populate it; it stays
+    // empty otherwise.
+    argmin: FlatRows<usize>,
+    row: Vec<usize>,
+    op: Op,
+}
+
+impl<T, Op> SparseTable<T, Op>
+where
+    T: Clone,
+    Op: IdempotentOp<T>,
+{
+    fn from_vec(seq: Vec<T>, op: Op) -> Self {
         let size = seq.len();
-        let mut rows = vec![seq];
+        let mut argmin = FlatRows::new();
+        argmin.push_row((0..size).collect());
+        let mut tracking_argmin = true;
+
+        let mut table = FlatRows::new();
+        table.push_row(seq);
         let mut i = 1;
 
         while (1 << i) <= size {
             let span = 1 << i;
-            let next_row: Vec<T>;
-            {
-                let prev_row = rows.last().unwrap();
-                next_row = prev_row.iter().zip(prev_row.iter().skip(span / 2))
-                                          .map(|(l, r)| min(l, r).clone())
-                                          .collect();
+            let prev_row = table.row(table.len() - 1);
+            let next_row = op.combine_row(prev_row, span / 2);
+            let next_idx = if tracking_argmin {
+                op.combine_row_argmin(prev_row, argmin.row(argmin.len() - 1), span / 2)
+            } else {
+                None
+            };
+            match next_idx {
+                Some(idx) => argmin.push_row(idx),
+                None => tracking_argmin = false,
             }
-            rows.push(next_row);
+            table.push_row(next_row);
             i += 1;
         }
+        if !tracking_argmin {
+            argmin = FlatRows::new();
+        }
 
         SparseTable {
-            table: rows,
+            table,
+            argmin,
             row: (0..size + 1).map(|x| {
                 if x != 0 {
                     highest_bit(x as u64)
                 } else {
                     0
                 }
-            }).collect()
+            }).collect(),
+            op
         }
     }
 
+    /// Builds a table over `seq` that answers queries using `op`.
+    pub fn with_op(seq: &[T], op: Op) -> Self {
+        Self::from_vec(seq.to_vec(), op)
+    }
+
+    /// Combines `op` over the half-open range `[l, r)` in O(1).
+    pub fn query(&self, l: usize, r: usize) -> T {
+        if l >= r {
+            panic!("No result for an empty range");
+        }
+        if r > self.table.row(0).len() {
+            panic!("Right bound is out of bounds");
+        }
+        let row = self.row[r - l];
+        let span = 1 << row;
+        self.op.combine(&self.table.row(row)[l], &self.table.row(row)[r - span])
+    }
+
+    /// Like `query`, but returns `default` instead of panicking when the
+    /// range is empty or starts past the end of the sequence.
+    pub fn query_with_default(&self, l: usize, r: usize, default: &T) -> T {
+        if l >= r || l >= self.table.row(0).len() {
+            return default.clone();
+        }
+        self.query(l, min(r, self.table.row(0).len()))
+    }
+}
+
+#[cfg(not(feature = "simd-nightly"))]
+impl<T> SparseTable<T, Min>
+where
+    T: Ord + Clone,
+{
     pub fn new(seq: &[T]) -> Self {
-        Self::from_vec(seq.to_vec())
+        Self::with_op(seq, Min)
     }
 
+    /// Returns the smallest element in `[l, r)` in O(1), by reference --
+    /// `Min` only ever selects one of its two inputs, so unlike the generic
+    /// `query`, this never needs to clone `T`.
     pub fn smallest(&self, l: usize, r: usize) -> &T {
+        if l >= r {
+            panic!("No result for an empty range");
+        }
+        if r > self.table.row(0).len() {
+            panic!("Right bound is out of bounds");
+        }
+        let row = self.row[r - l];
+        let span = 1 << row;
+        min(&self.table.row(row)[l], &self.table.row(row)[r - span])
+    }
+
+    pub fn smallest_with_default(&self, l: usize, r: usize, default: &T) -> T {
+        if l >= r || l >= self.table.row(0).len() {
+            return default.clone();
+        }
+        self.smallest(l, min(r, self.table.row(0).len())).clone()
+    }
+
+    /// Returns the position of the smallest element in `[l, r)`, breaking
+    /// ties toward the leftmost index.
+    pub fn smallest_index(&self, l: usize, r: usize) -> usize {
         if l >= r {
             panic!("No smallest element in an empty range");
         }
-        if r > self.table[0].len() {
+        if r > self.table.row(0).len() {
             panic!("Right bound is out of bounds");
         }
         let row = self.row[r - l];
         let span = 1 << row;
-        min(&self.table[row][l], &self.table[row][r - span])
+        let right = r - span;
+        if self.table.row(row)[l] <= self.table.row(row)[right] {
+            self.argmin.row(row)[l]
+        } else {
+            self.argmin.row(row)[right]
+        }
+    }
+
+    /// Like `smallest_index`, but returns `default` instead of panicking
+    /// when the range is empty or starts past the end of the sequence.
+    pub fn smallest_index_with_default(&self, l: usize, r: usize, default: usize) -> usize {
+        if l >= r || l >= self.table.row(0).len() {
+            return default;
+        }
+        self.smallest_index(l, min(r, self.table.row(0).len()))
+    }
+}
+
+#[cfg(feature = "simd-nightly")]
+impl<T> SparseTable<T, Min>
+where
+    T: Ord + Clone + 'static,
+{
+    pub fn new(seq: &[T]) -> Self {
+        Self::with_op(seq, Min)
+    }
+
+    /// Returns the smallest element in `[l, r)` in O(1), by reference --
+    /// `Min` only ever selects one of its two inputs, so unlike the generic
+    /// `query`, this never needs to clone `T`.
+    pub fn smallest(&self, l: usize, r: usize) -> &T {
+        if l >= r {
+            panic!("No result for an empty range");
+        }
+        if r > self.table.row(0).len() {
+            panic!("Right bound is out of bounds");
+        }
+        let row = self.row[r - l];
+        let span = 1 << row;
+        min(&self.table.row(row)[l], &self.table.row(row)[r - span])
     }
 
     pub fn smallest_with_default(&self, l: usize, r: usize, default: &T) -> T {
-        if l >= r || l >= self.table[0].len() {
+        if l >= r || l >= self.table.row(0).len() {
             return default.clone();
         }
-        self.smallest(l, min(r, self.table[0].len())).clone()
+        self.smallest(l, min(r, self.table.row(0).len())).clone()
+    }
+
+    /// Returns the position of the smallest element in `[l, r)`, breaking
+    /// ties toward the leftmost index.
+    pub fn smallest_index(&self, l: usize, r: usize) -> usize {
+        if l >= r {
+            panic!("No smallest element in an empty range");
+        }
+        if r > self.table.row(0).len() {
+            panic!("Right bound is out of bounds");
+        }
+        let row = self.row[r - l];
+        let span = 1 << row;
+        let right = r - span;
+        if self.table.row(row)[l] <= self.table.row(row)[right] {
+            self.argmin.row(row)[l]
+        } else {
+            self.argmin.row(row)[right]
+        }
+    }
+
+    /// Like `smallest_index`, but returns `default` instead of panicking
+    /// when the range is empty or starts past the end of the sequence.
+    pub fn smallest_index_with_default(&self, l: usize, r: usize, default: usize) -> usize {
+        if l >= r || l >= self.table.row(0).len() {
+            return default;
+        }
+        self.smallest_index(l, min(r, self.table.row(0).len()))
+    }
+}
+
+#[cfg(not(feature = "simd-nightly"))]
+impl<S, T> From<S> for SparseTable<T, Min>
+where
+    S: Into<Vec<T>>,
+    T: Ord + Clone,
+{
+    fn from(seq: S) -> Self {
+        SparseTable::<T, Min>::from_vec(seq.into(), Min)
     }
 }
 
-impl<S, T> From<S> for SparseTable<T> where S: Into<Vec<T>>, T: Ord + Clone {
+#[cfg(feature = "simd-nightly")]
+impl<S, T> From<S> for SparseTable<T, Min>
+where
+    S: Into<Vec<T>>,
+    T: Ord + Clone + 'static,
+{
     fn from(seq: S) -> Self {
-        SparseTable::<T>::from_vec(seq.into())
+        SparseTable::<T, Min>::from_vec(seq.into(), Min)
+    }
+}
+
+/// A sparse table for *any* associative operation, including ones (sums,
+/// products, matrix products, ...) that are not idempotent.
+///
+/// Unlike `SparseTable`, which overlaps two power-of-two ranges and relies
+/// on idempotence to not double-count their intersection, each level here
+/// cuts the sequence into disjoint blocks of length `2^(level + 1)` and
+/// folds each half of a block towards its midpoint. A query `[l, r]` picks
+/// the level at which `l` and `r` fall on opposite sides of a block
+/// midpoint -- `highest_bit(l ^ r)` -- and combines the two precomputed
+/// folds, which together cover `[l, r]` exactly once.
+pub struct DisjointSparseTable<T, Op> {
+    seq: Vec<T>,
+    table: FlatRows<T>,
+    op: Op,
+}
+
+impl<T, Op> DisjointSparseTable<T, Op>
+where
+    T: Clone,
+    Op: AssociativeOp<T>,
+{
+    pub fn with_op(seq: &[T], op: Op) -> Self {
+        let seq = seq.to_vec();
+        let size = seq.len();
+        let levels = if size > 1 { highest_bit((size - 1) as u64) + 1 } else { 0 };
+        let mut table = FlatRows::new();
+
+        for level in 0..levels {
+            let half = 1usize << level;
+            let block = half * 2;
+            let mut row = seq.clone();
+            let mut start = 0;
+
+            while start < size {
+                let end = min(start + block, size);
+                let mid = start + half;
+                let left_end = min(mid, end);
+
+                row[left_end - 1] = seq[left_end - 1].clone();
+                let mut i = left_end - 1;
+                while i > start {
+                    i -= 1;
+                    row[i] = op.combine(&seq[i], &row[i + 1]);
+                }
+
+                if end > mid {
+                    row[mid] = seq[mid].clone();
+                    for i in mid + 1..end {
+                        row[i] = op.combine(&row[i - 1], &seq[i]);
+                    }
+                }
+
+                start += block;
+            }
+
+            table.push_row(row);
+        }
+
+        DisjointSparseTable { seq, table, op }
+    }
+
+    /// Combines `op` over the inclusive range `[l, r]` in O(1).
+    pub fn query(&self, l: usize, r: usize) -> T {
+        if l > r {
+            panic!("No result for an empty range");
+        }
+        if r >= self.seq.len() {
+            panic!("Right bound is out of bounds");
+        }
+        if l == r {
+            return self.seq[l].clone();
+        }
+        let level = highest_bit((l ^ r) as u64);
+        self.op.combine(&self.table.row(level)[l], &self.table.row(level)[r])
+    }
+}
+
+/// A sparse table over an `R x C` matrix that answers the minimum over any
+/// axis-aligned rectangle `[r0, r1) x [c0, c1)` in O(1).
+///
+/// Level `(kr, kc)` -- stored as `table.row(kr * (max_kc + 1) + kc)`, a
+/// flat row-major `R(kr) x col_width[kc]` block (see [`FlatRows`]) --
+/// holds the minimum of the `2^kr x 2^kc` block of the matrix anchored at
+/// `(i, j)` for every valid `(i, j)`. It's built by first doubling along
+/// columns (level `(0, kc)`), then doubling those column-folded rows along
+/// the row dimension (level `(kr, kc)` for `kr > 0`), each step reusing
+/// the same power-of-two overlap trick as `SparseTable`. A query combines
+/// the four `2^kr x 2^kc` blocks anchored at the rectangle's corners.
+pub struct SparseTable2D<T> {
+    table: FlatRows<T>,
+    // `col_width[kc]` is the column count of every level `(_, kc)`.
+    col_width: Vec<usize>,
+    max_kc: usize,
+    rows: usize,
+    cols: usize,
+    row_level: Vec<usize>,
+    col_level: Vec<usize>,
+}
+
+impl<T> SparseTable2D<T>
+where
+    T: Ord + Clone,
+{
+    /// Builds a table over a row-major matrix; every row of `matrix` must
+    /// have the same length.
+    pub fn new(matrix: &[Vec<T>]) -> Self {
+        let rows = matrix.len();
+        let cols = if rows > 0 { matrix[0].len() } else { 0 };
+        if matrix.iter().any(|row| row.len() != cols) {
+            panic!("Every row of matrix must have the same length");
+        }
+        let max_kr = if rows > 0 { highest_bit(rows as u64) } else { 0 };
+        let max_kc = if cols > 0 { highest_bit(cols as u64) } else { 0 };
+
+        // Column-fold first: `by_col[kc]` is a flat, row-major `rows x
+        // col_width[kc]` block.
+        let mut col_width = Vec::with_capacity(max_kc + 1);
+        let mut by_col: Vec<Vec<T>> = Vec::with_capacity(max_kc + 1);
+
+        col_width.push(cols);
+        by_col.push(matrix.iter().flat_map(|row| row.iter().cloned()).collect());
+        for kc in 1..=max_kc {
+            let half = 1 << (kc - 1);
+            let prev_width = col_width[kc - 1];
+            let width = prev_width - half;
+            let prev = &by_col[kc - 1];
+            let mut next = Vec::with_capacity(rows * width);
+            for i in 0..rows {
+                for j in 0..width {
+                    next.push(min(&prev[i * prev_width + j], &prev[i * prev_width + j + half]).clone());
+                }
+            }
+            col_width.push(width);
+            by_col.push(next);
+        }
+
+        // Then row-fold each column-folded level, pushing every `(kr,
+        // kc)` block into `table` in `kr`-major, `kc`-minor order so
+        // `kr * (max_kc + 1) + kc` addresses it.
+        let mut table = FlatRows::new();
+        let mut level_data = by_col;
+        let mut level_rows = rows;
+
+        for kr in 0..=max_kr {
+            let next = if kr < max_kr {
+                let half = 1 << kr;
+                let next_rows = level_rows - half;
+                let mut built = Vec::with_capacity(max_kc + 1);
+                for (kc, &width) in col_width.iter().enumerate() {
+                    let prev = &level_data[kc];
+                    let mut row = Vec::with_capacity(next_rows * width);
+                    for i in 0..next_rows {
+                        for j in 0..width {
+                            row.push(min(&prev[i * width + j], &prev[(i + half) * width + j]).clone());
+                        }
+                    }
+                    built.push(row);
+                }
+                Some((built, next_rows))
+            } else {
+                None
+            };
+
+            for data in level_data.drain(..) {
+                table.push_row(data);
+            }
+            if let Some((built, next_rows)) = next {
+                level_data = built;
+                level_rows = next_rows;
+            }
+        }
+
+        SparseTable2D {
+            table,
+            col_width,
+            max_kc,
+            rows,
+            cols,
+            row_level: (0..rows + 1).map(|x| if x != 0 { highest_bit(x as u64) } else { 0 }).collect(),
+            col_level: (0..cols + 1).map(|x| if x != 0 { highest_bit(x as u64) } else { 0 }).collect(),
+        }
+    }
+
+    /// Returns the minimum over `[r0, r1) x [c0, c1)` in O(1).
+    pub fn smallest(&self, r0: usize, r1: usize, c0: usize, c1: usize) -> T {
+        if r0 >= r1 || c0 >= c1 {
+            panic!("No smallest element in an empty rectangle");
+        }
+        if r1 > self.rows || c1 > self.cols {
+            panic!("Rectangle is out of bounds");
+        }
+
+        let kr = self.row_level[r1 - r0];
+        let kc = self.col_level[c1 - c0];
+        let width = self.col_width[kc];
+        let block = self.table.row(kr * (self.max_kc + 1) + kc);
+        let top = r0;
+        let bottom = r1 - (1 << kr);
+        let left = c0;
+        let right = c1 - (1 << kc);
+
+        let candidates = [
+            &block[top * width + left],
+            &block[top * width + right],
+            &block[bottom * width + left],
+            &block[bottom * width + right],
+        ];
+        candidates.into_iter().min().unwrap().clone()
     }
 }
 
@@ -272,3 +868,186 @@ fn test_sparse_table() {
     assert_eq!(st2.smallest_with_default(7, 7, &42), 42);
     assert_eq!(st2.smallest_with_default(6, 7, &42), 42);
 }
+
+#[test]
+fn test_sparse_table_max() {
+    let st = SparseTable::with_op(&[1, 3, 2, 5, 4, 0], Max);
+    assert_eq!(st.query(0, 6), 5);
+    assert_eq!(st.query(0, 2), 3);
+    assert_eq!(st.query(2, 4), 5);
+    assert_eq!(st.query(4, 6), 4);
+    assert_eq!(st.query_with_default(6, 7, &42), 42);
+}
+
+#[test]
+fn test_sparse_table_gcd() {
+    // gcd is idempotent (gcd(x, x) == x), unlike sum, so it can go through
+    // `SparseTable` rather than `DisjointSparseTable`.
+    struct Gcd;
+    impl AssociativeOp<u64> for Gcd {
+        fn combine(&self, a: &u64, b: &u64) -> u64 {
+            let (mut a, mut b) = (*a, *b);
+            while b != 0 {
+                (a, b) = (b, a % b);
+            }
+            a
+        }
+    }
+    impl IdempotentOp<u64> for Gcd {}
+
+    let seq: Vec<u64> = vec![12, 8, 20, 16, 4, 24];
+    let st = SparseTable::with_op(&seq, Gcd);
+
+    for l in 0..seq.len() {
+        for r in l + 1..=seq.len() {
+            let expected = seq[l..r].iter().fold(0, |acc, &x| {
+                let (mut a, mut b) = (acc, x);
+                while b != 0 {
+                    (a, b) = (b, a % b);
+                }
+                a
+            });
+            assert_eq!(st.query(l, r), expected);
+        }
+    }
+}
+
+#[cfg(any(feature = "simd", feature = "simd-nightly"))]
+#[test]
+fn test_sparse_table_simd_row_matches_brute_force() {
+    // Long enough, and deliberately not a multiple of the lane/unroll
+    // width, to exercise both the fast-path chunks and the scalar
+    // remainder loop.
+    let seq: Vec<i32> = (0..137).map(|i| (i * 977) % 251).collect();
+    let st = SparseTable::<i32>::new(&seq);
+
+    for l in 0..seq.len() {
+        for r in l + 1..=seq.len() {
+            let expected = *seq[l..r].iter().min().unwrap();
+            assert_eq!(st.query(l, r), expected);
+        }
+    }
+}
+
+#[test]
+fn test_sparse_table_smallest_index() {
+    let seq = vec![5, 3, 3, 5, 1, 1, 5, 3];
+    let st = SparseTable::<i32>::new(&seq);
+
+    for l in 0..seq.len() {
+        for r in l + 1..=seq.len() {
+            let expected = seq[l..r]
+                .iter()
+                .enumerate()
+                .min_by_key(|&(i, v)| (*v, i))
+                .map(|(i, _)| l + i)
+                .unwrap();
+            assert_eq!(st.smallest_index(l, r), expected);
+        }
+    }
+
+    assert_eq!(st.smallest_index_with_default(8, 9, 42), 42);
+    assert_eq!(st.smallest_index_with_default(3, 3, 42), 42);
+}
+
+#[test]
+#[should_panic]
+fn test_sparse_table_smallest_index_empty_range_panics() {
+    let st = SparseTable::<i32>::new(&[1, 2, 3]);
+    st.smallest_index(1, 1);
+}
+
+#[test]
+fn test_sparse_table_2d() {
+    let matrix: Vec<Vec<i32>> = vec![
+        vec![5, 2, 8, 1, 9],
+        vec![3, 7, 4, 6, 0],
+        vec![9, 1, 2, 3, 4],
+        vec![6, 5, 4, 3, 2],
+    ];
+    let st = SparseTable2D::new(&matrix);
+
+    for r0 in 0..matrix.len() {
+        for r1 in r0 + 1..=matrix.len() {
+            for c0 in 0..matrix[0].len() {
+                for c1 in c0 + 1..=matrix[0].len() {
+                    let expected = matrix[r0..r1]
+                        .iter()
+                        .flat_map(|row| row[c0..c1].iter())
+                        .min()
+                        .unwrap();
+                    assert_eq!(st.smallest(r0, r1, c0, c1), *expected);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+#[should_panic]
+fn test_sparse_table_2d_empty_rectangle_panics() {
+    let st = SparseTable2D::new(&[vec![1, 2], vec![3, 4]]);
+    st.smallest(0, 0, 0, 1);
+}
+
+#[test]
+#[should_panic]
+fn test_sparse_table_2d_out_of_bounds_panics() {
+    let st = SparseTable2D::new(&[vec![1, 2], vec![3, 4]]);
+    st.smallest(0, 3, 0, 1);
+}
+
+#[test]
+#[should_panic]
+fn test_sparse_table_2d_ragged_matrix_panics() {
+    SparseTable2D::new(&[vec![1, 2, 3], vec![4, 5]]);
+}
+
+#[test]
+fn test_disjoint_sparse_table_sum() {
+    struct Sum;
+    impl AssociativeOp<i64> for Sum {
+        fn combine(&self, a: &i64, b: &i64) -> i64 {
+            a + b
+        }
+    }
+
+    // A length that is not a power of two, so some blocks are truncated.
+    let seq: Vec<i64> = vec![1, 2, 3, 4, 5, 6, 7];
+    let st = DisjointSparseTable::with_op(&seq, Sum);
+
+    for l in 0..seq.len() {
+        for r in l..seq.len() {
+            let expected: i64 = seq[l..=r].iter().sum();
+            assert_eq!(st.query(l, r), expected);
+        }
+    }
+}
+
+#[test]
+#[should_panic]
+fn test_disjoint_sparse_table_out_of_bounds_panics() {
+    struct Sum;
+    impl AssociativeOp<i64> for Sum {
+        fn combine(&self, a: &i64, b: &i64) -> i64 {
+            a + b
+        }
+    }
+
+    let st = DisjointSparseTable::with_op(&[1, 2, 3], Sum);
+    st.query(0, 3);
+}
+
+#[test]
+#[should_panic]
+fn test_disjoint_sparse_table_empty_range_panics() {
+    struct Sum;
+    impl AssociativeOp<i64> for Sum {
+        fn combine(&self, a: &i64, b: &i64) -> i64 {
+            a + b
+        }
+    }
+
+    let st = DisjointSparseTable::with_op(&[1, 2, 3], Sum);
+    st.query(1, 0);
+}
@@ -0,0 +1,154 @@
+//! Fast paths for `Min`/`Max` row construction, gated behind two distinct
+//! cargo features -- both are purely a construction-time optimization, so
+//! `SparseTable`'s public API and query results are identical either way:
+//!
+//! - `simd`: stable-toolchain-compatible. Unrolls the fold so the compiler
+//!   can auto-vectorize it, without relying on any unstable API.
+//! - `simd-nightly`: requires a nightly toolchain. Uses `core::simd`
+//!   (`#![feature(portable_simd)]`) for an explicit SIMD fold over
+//!   primitive integer widths, which is faster than auto-vectorization but
+//!   won't build on stable.
+
+#[cfg(feature = "simd")]
+use alloc::vec::Vec;
+
+#[cfg(feature = "simd")]
+const UNROLL: usize = 8;
+
+/// Element-wise `min(prev[i], prev[i + span])` for `i` in `0..prev.len() -
+/// span`, unrolled by [`UNROLL`] elements per iteration.
+#[cfg(feature = "simd")]
+pub(crate) fn min_row_unrolled<T: Ord + Clone>(prev: &[T], span: usize) -> Vec<T> {
+    fold_unrolled(prev, span, Ord::min)
+}
+
+/// Same as [`min_row_unrolled`], but for `max`.
+#[cfg(feature = "simd")]
+pub(crate) fn max_row_unrolled<T: Ord + Clone>(prev: &[T], span: usize) -> Vec<T> {
+    fold_unrolled(prev, span, Ord::max)
+}
+
+#[cfg(feature = "simd")]
+fn fold_unrolled<T: Clone>(prev: &[T], span: usize, op: fn(T, T) -> T) -> Vec<T> {
+    let len = prev.len() - span;
+    let mut out = Vec::with_capacity(len);
+
+    let mut i = 0;
+    while i + UNROLL <= len {
+        for j in 0..UNROLL {
+            out.push(op(prev[i + j].clone(), prev[i + span + j].clone()));
+        }
+        i += UNROLL;
+    }
+    while i < len {
+        out.push(op(prev[i].clone(), prev[i + span].clone()));
+        i += 1;
+    }
+
+    out
+}
+
+#[cfg(feature = "simd-nightly")]
+pub(crate) use nightly::{max_row, min_row};
+
+#[cfg(feature = "simd-nightly")]
+mod nightly {
+    //! `core::simd` only knows how to operate on concrete primitive types,
+    //! while `SparseTable` is generic over any `T: Ord + Clone`, so
+    //! `min_row`/`max_row` check `T`'s `TypeId` against the primitives we
+    //! have a lane width for and fall back to `None` (letting the caller
+    //! use the scalar path) for everything else.
+
+    use alloc::vec::Vec;
+    use core::any::TypeId;
+    use core::mem;
+    use core::simd::cmp::SimdOrd;
+    use core::simd::Simd;
+
+    const LANES: usize = 8;
+
+    macro_rules! try_primitive {
+        ($T:ident, $prev:ident, $span:ident, $fold:ident, $ty:ty) => {
+            if TypeId::of::<$T>() == TypeId::of::<$ty>() {
+                // SAFETY: the `TypeId` check above proves `$T` is exactly
+                // `$ty`, so `&[$T]` and `&[$ty]` have identical layout.
+                let prev: &[$ty] = unsafe { &*($prev as *const [$T] as *const [$ty]) };
+                let row = $fold(prev, $span);
+                // SAFETY: `row` is `Vec<$ty>` and `$T == $ty`.
+                return Some(unsafe { mem::transmute::<Vec<$ty>, Vec<$T>>(row) });
+            }
+        };
+    }
+
+    macro_rules! for_each_lane_primitive {
+        ($T:ident, $prev:ident, $span:ident, $fold:ident) => {
+            try_primitive!($T, $prev, $span, $fold, i8);
+            try_primitive!($T, $prev, $span, $fold, i16);
+            try_primitive!($T, $prev, $span, $fold, i32);
+            try_primitive!($T, $prev, $span, $fold, i64);
+            try_primitive!($T, $prev, $span, $fold, isize);
+            try_primitive!($T, $prev, $span, $fold, u8);
+            try_primitive!($T, $prev, $span, $fold, u16);
+            try_primitive!($T, $prev, $span, $fold, u32);
+            try_primitive!($T, $prev, $span, $fold, u64);
+            try_primitive!($T, $prev, $span, $fold, usize);
+        };
+    }
+
+    /// Element-wise `min(prev[i], prev[i + span])` for `i` in
+    /// `0..prev.len() - span`, or `None` if `T` isn't one of the primitive
+    /// integers we carry a SIMD lane for.
+    pub(crate) fn min_row<T: 'static + Clone>(prev: &[T], span: usize) -> Option<Vec<T>> {
+        for_each_lane_primitive!(T, prev, span, fold_min);
+        None
+    }
+
+    /// Same as [`min_row`], but for `max`.
+    pub(crate) fn max_row<T: 'static + Clone>(prev: &[T], span: usize) -> Option<Vec<T>> {
+        for_each_lane_primitive!(T, prev, span, fold_max);
+        None
+    }
+
+    fn fold_min<L>(prev: &[L], span: usize) -> Vec<L>
+    where
+        L: core::simd::SimdElement + Ord + Copy,
+        Simd<L, LANES>: SimdOrd,
+    {
+        fold(prev, span, Simd::simd_min, Ord::min)
+    }
+
+    fn fold_max<L>(prev: &[L], span: usize) -> Vec<L>
+    where
+        L: core::simd::SimdElement + Ord + Copy,
+        Simd<L, LANES>: SimdOrd,
+    {
+        fold(prev, span, Simd::simd_max, Ord::max)
+    }
+
+    fn fold<L>(
+        prev: &[L],
+        span: usize,
+        simd_op: impl Fn(Simd<L, LANES>, Simd<L, LANES>) -> Simd<L, LANES>,
+        scalar_op: impl Fn(L, L) -> L,
+    ) -> Vec<L>
+    where
+        L: core::simd::SimdElement + Copy,
+    {
+        let len = prev.len() - span;
+        let mut out = Vec::with_capacity(len);
+
+        let mut i = 0;
+        while i + LANES <= len {
+            let l = Simd::<L, LANES>::from_slice(&prev[i..i + LANES]);
+            let r = Simd::<L, LANES>::from_slice(&prev[i + span..i + span + LANES]);
+            out.extend_from_slice(simd_op(l, r).as_array());
+            i += LANES;
+        }
+        while i < len {
+            out.push(scalar_op(prev[i], prev[i + span]));
+            i += 1;
+        }
+
+        out
+    }
+}